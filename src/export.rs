@@ -0,0 +1,111 @@
+use std::cmp::Reverse;
+use std::fmt::Display;
+
+use float_ord::FloatOrd;
+use serde::Serialize;
+
+use crate::monte_carlo::MonteCarloTreeNode;
+use crate::Game;
+
+/// Controls how much of a tree `MonteCarloTreeNode::to_json` walks, so a
+/// large completed search doesn't dump an unreasonably large document.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TreeExportOptions {
+    /// Stop descending after this many levels below the node being exported.
+    /// `None` walks the whole tree.
+    pub max_depth: Option<usize>,
+    /// Keep only the `k` most-visited children at each level. `None` keeps
+    /// all of them.
+    pub top_k_children: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ExportedNode {
+    choice: Option<String>,
+    games: f64,
+    cumulative_reward: f64,
+    win_rate: f64,
+    children: Vec<ExportedNode>,
+}
+
+impl<G: Game> MonteCarloTreeNode<G>
+where
+    G::Choice: Display,
+{
+    /// Exports this node and its descendants as a `serde_json::Value`, for
+    /// feeding a completed search into an external tree viewer. `choice` is
+    /// rendered with `Display` rather than requiring `Choice: Serialize`, so
+    /// any game's choice type works without extra derives.
+    pub fn to_json(&self, options: TreeExportOptions) -> serde_json::Value {
+        serde_json::to_value(self.export(options.max_depth, options.top_k_children))
+            .expect("a tree of plain numbers and strings always serializes")
+    }
+
+    fn export(&self, max_depth: Option<usize>, top_k_children: Option<usize>) -> ExportedNode {
+        let win_rate = if self.games > 0.0 {
+            self.cumulative_reward / self.games
+        } else {
+            0.0
+        };
+
+        let children = if max_depth == Some(0) {
+            Vec::new()
+        } else {
+            let mut children: Vec<&Self> = self.children.values().collect();
+            children.sort_by_key(|child| Reverse(FloatOrd(child.games)));
+            if let Some(top_k_children) = top_k_children {
+                children.truncate(top_k_children);
+            }
+            children
+                .into_iter()
+                .map(|child| child.export(max_depth.map(|depth| depth - 1), top_k_children))
+                .collect()
+        };
+
+        ExportedNode {
+            choice: self.choice.as_ref().map(|choice| choice.to_string()),
+            games: self.games,
+            cumulative_reward: self.cumulative_reward,
+            win_rate,
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monte_carlo::{Budget, MonteCarloTreeSearch, VanillaMcts};
+    use crate::test_support::BinaryTreeDepthThreeZeroWins;
+
+    #[test]
+    fn exports_win_rate_and_choice() {
+        let game = BinaryTreeDepthThreeZeroWins::new();
+        let mut mcts: VanillaMcts<BinaryTreeDepthThreeZeroWins> = VanillaMcts::new();
+        let tree = mcts.build_tree(game, Budget::Iterations(16));
+
+        let exported = tree.to_json(TreeExportOptions::default());
+        let winning_child = exported["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|child| child["choice"] == "1")
+            .unwrap();
+        assert_eq!(winning_child["win_rate"], winning_child["cumulative_reward"].as_f64().unwrap() / winning_child["games"].as_f64().unwrap());
+    }
+
+    #[test]
+    fn prunes_to_max_depth_and_top_k() {
+        let game = BinaryTreeDepthThreeZeroWins::new();
+        let mut mcts: VanillaMcts<BinaryTreeDepthThreeZeroWins> = VanillaMcts::new();
+        let tree = mcts.build_tree(game, Budget::Iterations(16));
+
+        let exported = tree.to_json(TreeExportOptions {
+            max_depth: Some(1),
+            top_k_children: Some(1),
+        });
+        let children = exported["children"].as_array().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0]["children"].as_array().unwrap().len(), 0);
+    }
+}
@@ -2,7 +2,8 @@ use std::hash::Hash;
 
 use rand::{seq::SliceRandom, thread_rng};
 
-pub use crate::{MonteCarloTreeSearch, VanillaMcts};
+use crate::monte_carlo::most_promising_choice;
+pub use crate::{Budget, MonteCarloTreeNode, MonteCarloTreeSearch, VanillaMcts};
 
 pub trait Game: Clone {
     type Choice: Eq + Hash + Clone;
@@ -41,12 +42,60 @@ pub trait Game: Clone {
         true
     }
 
+    // Whether `get_determinization`/`choice_is_available` are guaranteed to
+    // answer consistently for this exact game state. Imperfect-information
+    // games should override this to `false`, since a subtree built from one
+    // determinization doesn't necessarily describe the real game once a move
+    // reveals more of it. Used by `run_with_reuse` to decide whether keeping
+    // a subtree across moves is safe.
+    fn is_deterministic(&self) -> bool {
+        true
+    }
+
     // Meant for quick debugging purposes
     fn run(&mut self, iterations: usize) {
         let mut mcts: VanillaMcts<Self> = VanillaMcts::new();
         while !self.is_terminal() {
-            let (choice, _) = mcts.monte_carlo_tree_search(self.clone(), iterations);
+            let (choice, _) = mcts.monte_carlo_tree_search(self.clone(), Budget::Iterations(iterations));
+            self.apply_choice(&choice);
+        }
+    }
+
+    // Like `run`, but keeps the subtree under the chosen move as a warm
+    // start for the next search instead of discarding the whole tree.
+    // Falls back to a fresh tree every move for non-deterministic games
+    // (see `is_deterministic`), since their retained statistics may no
+    // longer describe reality once the real move is known.
+    fn run_with_reuse(&mut self, budget: Budget) {
+        let mut mcts: VanillaMcts<Self> = VanillaMcts::new();
+        let mut tree: Option<MonteCarloTreeNode<Self>> = None;
+
+        while !self.is_terminal() {
+            let mut node = tree
+                .take()
+                .unwrap_or_else(|| MonteCarloTreeNode::new(self.get_active_player_id(), None));
+            mcts.grow_tree(&mut node, self.clone(), budget);
+
+            let choice = most_promising_choice(&node);
             self.apply_choice(&choice);
+
+            tree = if self.is_deterministic() {
+                let mut child = node.children.remove(&choice).unwrap();
+                child.choice = None;
+                // The detached child may be only partly expanded, and `expand`
+                // never adds children to a root that already has some.
+                if !self.is_terminal() {
+                    let active_player = self.get_active_player_id();
+                    for missing in self.get_all_choices() {
+                        child.children.entry(missing.clone()).or_insert_with(|| {
+                            MonteCarloTreeNode::new(active_player, Some(missing))
+                        });
+                    }
+                }
+                Some(child)
+            } else {
+                None
+            };
         }
     }
 }
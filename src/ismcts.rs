@@ -0,0 +1,251 @@
+use rustc_hash::FxHashMap;
+
+use crate::monte_carlo::{most_promising_choice, Budget, MonteCarloTreeNode, MonteCarloTreeSearch, VanillaMcts};
+use crate::stats::MctsStats;
+use crate::Game;
+
+/// Single-Observer Information Set MCTS.
+///
+/// This is exactly [`VanillaMcts`]: `build_tree` already samples a fresh
+/// `get_determinization` every iteration and `select` already skips
+/// children whose choice fails `choice_is_available`. `expand` counts every
+/// choice from the sampled world's `get_all_choices` as available; it does
+/// not consult `choice_is_available`, so a choice that is listed but
+/// unavailable still raises its availability count. The alias exists so imperfect-information callers
+/// can reach for a name that says what they're relying on instead of
+/// rediscovering it from `Game`'s doc comments.
+pub type SingleObserverIsmcts<G> = VanillaMcts<G>;
+
+/// Multiple-Observer Information Set MCTS: one tree per player, each built
+/// from determinizations sampled from that player's own information set.
+///
+/// This avoids a common ISMCTS mistake: deciding every player's simulated
+/// move from a single shared tree whose determinizations are all sampled
+/// from one observer means opponents effectively plan with a belief that
+/// isn't theirs. Here, each player's tree only ever sees the world through
+/// `get_determinization(player_id)`.
+///
+/// Note this keeps the trees independent rather than interleaving them
+/// within a single playout (as in Cowling et al.'s original MO-ISMCTS) — a
+/// simpler design that still gives every player's plan its own honest view
+/// of hidden information, at the cost of not sharing statistics between
+/// the players' searches of the same underlying game tree.
+pub struct MultiObserverIsmcts<G: Game>
+where
+    G::PlayerId: Eq + std::hash::Hash,
+{
+    trees: FxHashMap<G::PlayerId, MonteCarloTreeNode<G>>,
+}
+
+impl<G: Game> MultiObserverIsmcts<G>
+where
+    G::PlayerId: Eq + std::hash::Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            trees: Default::default(),
+        }
+    }
+
+    /// Builds one tree per entry in `observers`, each determinized from
+    /// that player's own information set.
+    pub fn build_trees(&mut self, game: &G, iterations: usize, observers: &[G::PlayerId]) {
+        for &observer in observers {
+            self.trees
+                .insert(observer, build_observer_tree(game, iterations, observer));
+        }
+    }
+
+    pub fn tree_for(&self, observer: G::PlayerId) -> Option<&MonteCarloTreeNode<G>> {
+        self.trees.get(&observer)
+    }
+
+    /// Builds a tree for every player in `observers`, then picks the
+    /// active player's move from their own tree.
+    pub fn monte_carlo_tree_search_multi_observer(
+        &mut self,
+        game: G,
+        iterations: usize,
+        observers: &[G::PlayerId],
+    ) -> (G::Choice, MctsStats) {
+        self.build_trees(&game, iterations, observers);
+        let tree = self
+            .tree_for(game.get_active_player_id())
+            .expect("observers must include the active player");
+
+        let selected_choice = most_promising_choice(tree);
+        let selected_child = &tree.children[&selected_choice];
+
+        (
+            selected_child.choice.clone().unwrap(),
+            MctsStats {
+                tree_cumulative_reward: tree.cumulative_reward,
+                tree_games: tree.games,
+            },
+        )
+    }
+}
+
+impl<G: Game> Default for MultiObserverIsmcts<G>
+where
+    G::PlayerId: Eq + std::hash::Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Like `MonteCarloTreeSearch::build_tree`, but determinizes from a fixed
+// `observer`'s information set instead of whoever is active at the root.
+fn build_observer_tree<G: Game>(
+    game: &G,
+    iterations: usize,
+    observer: G::PlayerId,
+) -> MonteCarloTreeNode<G> {
+    let mut mcts: VanillaMcts<G> = VanillaMcts::new();
+    let player_id = game.get_active_player_id();
+    let mut tree: MonteCarloTreeNode<G> = MonteCarloTreeNode::new(player_id, None);
+
+    for _ in 0..iterations {
+        let determinization = game.get_determinization(observer);
+        let result = mcts.iteration(&mut tree, determinization);
+        mcts.after_iteration(&result);
+    }
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::seq::SliceRandom;
+    use rand::thread_rng;
+
+    // A minimal hidden-card game: player 0 has already secretly played a
+    // card, and player 1 must guess which one. `played` holds the ground
+    // truth, but player 1's determinization must never see it directly.
+    #[derive(Clone)]
+    struct HiddenCardGame {
+        hand: Vec<usize>,
+        played: Option<usize>,
+        guess: Option<usize>,
+    }
+
+    impl HiddenCardGame {
+        fn guesser_to_move(true_card: usize) -> Self {
+            HiddenCardGame {
+                hand: vec![true_card],
+                played: Some(true_card),
+                guess: None,
+            }
+        }
+    }
+
+    impl Game for HiddenCardGame {
+        type Choice = usize;
+        type PlayerId = usize;
+
+        fn get_all_choices(&self) -> Vec<Self::Choice> {
+            vec![0, 1, 2]
+        }
+
+        fn apply_choice(&mut self, choice: &Self::Choice) {
+            if self.played.is_none() {
+                self.played = Some(*choice);
+            } else {
+                self.guess = Some(*choice);
+            }
+        }
+
+        fn get_active_player_id(&self) -> Self::PlayerId {
+            if self.played.is_none() {
+                0
+            } else {
+                1
+            }
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.guess.is_some()
+        }
+
+        fn reward_for(&self, player_id: Self::PlayerId) -> f64 {
+            let guesser_won = self.guess == self.played;
+            if player_id == 1 {
+                if guesser_won {
+                    1.0
+                } else {
+                    0.0
+                }
+            } else if guesser_won {
+                0.0
+            } else {
+                1.0
+            }
+        }
+
+        fn choice_is_available(&self, choice: &Self::Choice) -> bool {
+            if self.played.is_none() {
+                self.hand.contains(choice)
+            } else {
+                true
+            }
+        }
+
+        // Player 1 never learns the true `played` card from a
+        // determinization: every sampled world replaces it with a guess of
+        // its own, just like it would for a real hidden hand.
+        fn get_determinization(&self, from_perspective: Self::PlayerId) -> Self {
+            if from_perspective == 1 {
+                let mut determinized = self.clone();
+                let mut rng = thread_rng();
+                determinized.played = Some(*[0, 1, 2].choose(&mut rng).unwrap());
+                determinized
+            } else {
+                self.clone()
+            }
+        }
+
+        fn shuffle_on_expand(&self) -> bool {
+            false
+        }
+
+        fn is_deterministic(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn single_observer_does_not_peek_at_the_true_card() {
+        let game = HiddenCardGame::guesser_to_move(2);
+        let mut mcts: SingleObserverIsmcts<HiddenCardGame> = SingleObserverIsmcts::new();
+        let tree = mcts.build_tree(game, Budget::Iterations(300));
+
+        // If the search could see the real `played` value it would collapse
+        // onto a single guess after trying each guess once; since every
+        // iteration re-determinizes it away, all three guesses keep getting
+        // revisited.
+        assert_eq!(tree.children.len(), 3);
+        assert!(tree.children.values().all(|child| child.games > 1.0));
+    }
+
+    #[test]
+    fn multi_observer_determinizes_each_tree_from_its_own_player() {
+        let true_card = 2;
+        let game = HiddenCardGame::guesser_to_move(true_card);
+        let mut mcts: MultiObserverIsmcts<HiddenCardGame> = MultiObserverIsmcts::new();
+        mcts.build_trees(&game, 300, &[0, 1]);
+
+        // Player 0 played the card themself, so get_determinization(0)
+        // hands their tree the same fully-known world on every iteration;
+        // it should settle on guessing the true card.
+        let dealer_tree = mcts.tree_for(0).unwrap();
+        assert_eq!(most_promising_choice(dealer_tree), true_card);
+
+        // Player 1 is the guesser and their own determinization keeps
+        // hiding `played` from them, so their tree still explores all
+        // three guesses, same as the single-observer case above.
+        let guesser_tree = mcts.tree_for(1).unwrap();
+        assert_eq!(guesser_tree.children.len(), 3);
+        assert!(guesser_tree.children.values().all(|child| child.games > 0.0));
+    }
+}
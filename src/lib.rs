@@ -1,8 +1,20 @@
 mod monte_carlo;
+mod parallel;
+mod rave;
+mod selection;
 mod stats;
 mod game;
 mod progressive_bias;
+mod ismcts;
+mod export;
+#[cfg(test)]
+mod test_support;
 
-pub use monte_carlo::{MonteCarloTreeNode, MonteCarloTreeSearch, VanillaMcts};
+pub use monte_carlo::{Budget, MonteCarloTreeNode, MonteCarloTreeSearch, VanillaMcts};
+pub use parallel::{ParallelMcts, ParallelStrategy};
+pub use rave::RaveMcts;
+pub use selection::{SelectionPolicy, Ucb1Policy, Ucb1TunedPolicy};
 pub use game::Game;
 pub use progressive_bias::ProgressiveBiasPolicy;
+pub use ismcts::{MultiObserverIsmcts, SingleObserverIsmcts};
+pub use export::TreeExportOptions;
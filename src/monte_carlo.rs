@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
@@ -8,24 +9,43 @@ use rustc_hash::FxHashMap;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 
+use crate::selection::{SelectionPolicy, Ucb1Policy};
 use crate::stats::MctsStats;
 use crate::Game;
 
+/// How much search to run before committing to a move.
+///
+/// `Iterations` is the usual fixed-work budget; `Duration` instead checks a
+/// deadline between iterations, for play under a move clock. Since a check
+/// only happens between iterations (not mid-iteration), a single very slow
+/// iteration can still overrun the deadline.
+#[derive(Debug, Clone, Copy)]
+pub enum Budget {
+    Iterations(usize),
+    Duration(std::time::Duration),
+}
+
+impl Budget {
+    pub(crate) fn keep_going(&self, iterations_done: usize, start: std::time::Instant) -> bool {
+        match self {
+            Budget::Iterations(iterations) => iterations_done < *iterations,
+            Budget::Duration(duration) => start.elapsed() < *duration,
+        }
+    }
+}
+
 pub trait MonteCarloTreeSearch {
     type Game: Game;
 
     fn monte_carlo_tree_search(
         &mut self,
         game: Self::Game,
-        iterations: usize,
+        budget: Budget,
     ) -> (<Self::Game as Game>::Choice, MctsStats) {
-        let tree = self.build_tree(game, iterations);
+        let tree = self.build_tree(game, budget);
 
-        let selected_child = tree
-            .children
-            .values()
-            .max_by_key(|child| FloatOrd(child.games))
-            .unwrap();
+        let selected_choice = most_promising_choice(&tree);
+        let selected_child = &tree.children[&selected_choice];
 
         let mut stats: Stats<f64> = Stats::new();
         tree.children
@@ -52,17 +72,30 @@ pub trait MonteCarloTreeSearch {
     fn build_tree(
         &mut self,
         game: Self::Game,
-        iterations: usize,
+        budget: Budget,
     ) -> MonteCarloTreeNode<Self::Game>  {
         let player_id = game.get_active_player_id();
         let mut tree: MonteCarloTreeNode<Self::Game> = MonteCarloTreeNode::new(player_id, None);
+        self.grow_tree(&mut tree, game, budget);
+        tree
+    }
 
-        for _ in 0..iterations {
+    // Like `build_tree`, but grows an existing tree in place instead of
+    // starting from a fresh root. `build_tree` is just this applied to a
+    // freshly created root; overriders that need extra per-iteration
+    // bookkeeping (e.g. `RaveMcts` clearing its playout buffer) should do
+    // so in `after_iteration` rather than overriding this loop, so the
+    // bookkeeping still runs when `grow_tree` is called directly (as
+    // `Game::run_with_reuse` does).
+    fn grow_tree(&mut self, tree: &mut MonteCarloTreeNode<Self::Game>, game: Self::Game, budget: Budget) {
+        let start = std::time::Instant::now();
+        let mut iterations_done = 0;
+        while budget.keep_going(iterations_done, start) {
             let determinization = game.get_determinization(game.get_active_player_id());
-            let game = self.iteration(&mut tree, determinization);
+            let game = self.iteration(tree, determinization);
             self.after_iteration(&game);
+            iterations_done += 1;
         }
-        tree
     }
 
     fn after_iteration(&mut self, _game: &Self::Game) {}
@@ -75,9 +108,11 @@ pub trait MonteCarloTreeSearch {
     ) -> Self::Game {
         if game.is_terminal() {
             self.record_outcome(node, &game);
+            node.proven = node.proven.or_else(|| proven_outcome_for(&game, node.player_id));
             return game;
         }
 
+        let game_at_node = game.clone();
         let choices = node.expand(&game, game.shuffle_on_expand());
 
         let best_child = self.select(node, &game, choices);
@@ -86,8 +121,12 @@ pub trait MonteCarloTreeSearch {
 
         let game = if best_child.games == 0.0 {
             //println!("Rolling out {}", best_child.id);
+            let reached_terminal = game.is_terminal();
             let game = self.rollout(best_child, game);
             self.record_outcome(best_child, &game);
+            if reached_terminal {
+                best_child.proven = proven_outcome_for(&game, best_child.player_id);
+            }
             game
         } else {
             //println!("Recursing from {} to {}", node_id, best_child.id);
@@ -95,6 +134,7 @@ pub trait MonteCarloTreeSearch {
         };
         //println!("Recording at {} after handling {}", node_id, best_child.id);
         self.record_outcome(node, &game);
+        node.update_proven_status(&game_at_node);
         return game;
     }
 
@@ -117,23 +157,12 @@ pub trait MonteCarloTreeSearch {
         child: &MonteCarloTreeNode<Self::Game>,
     ) -> f64 {
         let c = 0.4;
-        let cumulative_reward = child.cumulative_reward;
-        let games = child.games;
-        let total_game_count = if parent.is_root() {
-            // The root is always fully expanded and the availability of nodes does not change
-            parent.games
-        } else {
-            *parent
-                .choice_availability_count
-                .get(child.choice.as_ref().unwrap())
-                .unwrap() as f64
-        };
-        //upper_confidence_bound(cumulative_reward, games, total_game_count, c)
-        //let c = 0.4;
-        //let cumulative_reward = child.cumulative_reward;
-        //let games = child.games;
-        //let total_game_count = parent.games;
-        upper_confidence_bound(cumulative_reward, games, total_game_count, c)
+        upper_confidence_bound(
+            child.cumulative_reward,
+            child.games,
+            total_available_games(parent, child),
+            c,
+        )
     }
 
     fn select<'a>(
@@ -142,10 +171,22 @@ pub trait MonteCarloTreeSearch {
         game: &'_ Self::Game,
         choices: Option<Vec<<Self::Game as Game>::Choice>>,
     ) -> &'a mut MonteCarloTreeNode<Self::Game> {
+        // A proven win can be taken immediately; no need to keep exploring.
+        if let Some(winning_choice) = node
+            .children
+            .iter()
+            .filter(|(choice, _)| game.choice_is_available(choice))
+            .find(|(_, child)| child.proven == Some(Ordering::Greater))
+            .map(|(choice, _)| choice.clone())
+        {
+            return node.children.get_mut(&winning_choice).unwrap();
+        }
+
         let selected_choice = node
             .children
             .iter()
             .filter(|(choice, _)| game.choice_is_available(choice))
+            .filter(|(_, child)| child.proven != Some(Ordering::Less))
             .max_by_key(|(_, child)| {
                 // TODO make this short-circuit if we find a child with an infinite value (e.g., a child not yet explored)
                 FloatOrd(if child.games == 0.0 {
@@ -154,9 +195,16 @@ pub trait MonteCarloTreeSearch {
                     self.get_selection_value(game, node, child)
                 })
             })
-            .map(|(choice, _)| choice)
-            .unwrap()
-            .clone();
+            .map(|(choice, _)| choice.clone())
+            .unwrap_or_else(|| {
+                // Every available child is a proven loss; any of them is as good as another.
+                node.children
+                    .iter()
+                    .filter(|(choice, _)| game.choice_is_available(choice))
+                    .map(|(choice, _)| choice.clone())
+                    .next()
+                    .unwrap()
+            });
         node.children.get_mut(&selected_choice).unwrap()
     }
 
@@ -190,36 +238,78 @@ pub trait MonteCarloTreeSearch {
         node: &mut MonteCarloTreeNode<Self::Game>,
         game: &Self::Game,
     ) {
-        node.cumulative_reward += game.reward_for(node.player_id);
+        let reward = game.reward_for(node.player_id);
+        node.cumulative_reward += reward;
+        node.sum_squared_reward += reward * reward;
         node.games += 1.0;
     }
 }
 
-#[derive(Clone, Copy, Default)]
-pub struct VanillaMcts<G: Game> {
+#[derive(Clone, Copy)]
+pub struct VanillaMcts<G: Game, P: SelectionPolicy<G> = Ucb1Policy> {
     phantom: PhantomData<G>,
+    policy: P,
 }
 
-impl<G: Game> VanillaMcts<G> {
+impl<G: Game, P: SelectionPolicy<G> + Default> Default for VanillaMcts<G, P> {
+    fn default() -> Self {
+        Self::with_policy(P::default())
+    }
+}
+
+impl<G: Game, P: SelectionPolicy<G> + Default> VanillaMcts<G, P> {
     pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<G: Game, P: SelectionPolicy<G>> VanillaMcts<G, P> {
+    pub fn with_policy(policy: P) -> Self {
         Self {
             phantom: PhantomData,
+            policy,
         }
     }
 }
 
-impl<G: Game> MonteCarloTreeSearch for VanillaMcts<G> {
+impl<G: Game> VanillaMcts<G, Ucb1Policy> {
+    pub fn with_c(c: f64) -> Self {
+        Self::with_policy(Ucb1Policy::new(c))
+    }
+}
+
+impl<G: Game, P: SelectionPolicy<G>> MonteCarloTreeSearch for VanillaMcts<G, P> {
     type Game = G;
+
+    fn get_selection_value(
+        &self,
+        game: &Self::Game,
+        parent: &MonteCarloTreeNode<Self::Game>,
+        child: &MonteCarloTreeNode<Self::Game>,
+    ) -> f64 {
+        self.policy.value(parent, child, game)
+    }
 }
 
 #[derive(Debug)]
 pub struct MonteCarloTreeNode<G: Game> {
     pub games: f64,
     pub cumulative_reward: f64,
+    // Sum of squared per-playout rewards, used by variance-aware selection
+    // policies such as UCB1-tuned.
+    pub sum_squared_reward: f64,
     pub player_id: G::PlayerId,
     pub choice: Option<G::Choice>,
     pub children: FxHashMap<G::Choice, Self>,
     pub choice_availability_count: FxHashMap<G::Choice, usize>,
+    // MCTS-Solver: a proven outcome for `player_id`, i.e. the player who chose
+    // to reach this node. `Greater` means they can force a win from here,
+    // `Less` means they are forced to lose no matter what they do.
+    pub proven: Option<Ordering>,
+    // RAVE/AMAF: (games, cumulative_reward) for each choice that the player
+    // moving at this node played anywhere later in a playout through this
+    // node, regardless of whether it was actually chosen here.
+    pub amaf: FxHashMap<G::Choice, (f64, f64)>,
 }
 
 impl<G> MonteCarloTreeNode<G>
@@ -230,15 +320,62 @@ where
         Self {
             games: 0.0,
             cumulative_reward: 0.0,
+            sum_squared_reward: 0.0,
             player_id: owner,
             choice,
             children: Default::default(),
             choice_availability_count: Default::default(),
+            proven: None,
+            amaf: Default::default(),
+        }
+    }
+
+    // Recomputes `proven` from the children available in `game_at_node`
+    // (the game state at this node, before a choice is applied). A node
+    // becomes a proven win if every choice available to the player who
+    // moves here leads to a proven loss for them; it becomes a proven loss
+    // as soon as any available choice leads to a proven win for them.
+    //
+    // Only children that are available right now are considered, since
+    // under determinization some children may be unavailable in this
+    // playout even though they were reachable in others.
+    pub(crate) fn update_proven_status(&mut self, game_at_node: &G) {
+        if self.proven.is_some() || self.children.is_empty() {
+            return;
+        }
+
+        let available_children: Vec<&Self> = self
+            .children
+            .iter()
+            .filter(|(choice, _)| game_at_node.choice_is_available(choice))
+            .map(|(_, child)| child)
+            .collect();
+
+        if available_children
+            .iter()
+            .any(|child| child.proven == Some(Ordering::Greater))
+        {
+            self.proven = Some(Ordering::Less);
+            return;
+        }
+
+        let available_choice_count = game_at_node
+            .get_all_choices()
+            .into_iter()
+            .filter(|choice| game_at_node.choice_is_available(choice))
+            .count();
+        let fully_expanded = available_children.len() == available_choice_count;
+        let all_losses = available_children
+            .iter()
+            .all(|child| child.proven == Some(Ordering::Less));
+
+        if fully_expanded && all_losses && !available_children.is_empty() {
+            self.proven = Some(Ordering::Greater);
         }
     }
 
     // Returns the choices available for non-root nodes
-    fn expand(&mut self, game: &G, shuffle: bool) -> Option<Vec<<G as Game>::Choice>> {
+    pub(crate) fn expand(&mut self, game: &G, shuffle: bool) -> Option<Vec<<G as Game>::Choice>> {
         if self.is_root() && !self.children.is_empty() {
             return None;
         }
@@ -273,6 +410,64 @@ where
     }
 }
 
+// The number of times `child`'s choice has been available to select from
+// `parent`, used as the "N" in UCB-style formulas. The root is always fully
+// expanded and its availability never changes, so `parent.games` is exact;
+// elsewhere we fall back to the tracked availability count.
+pub(crate) fn total_available_games<G: Game>(
+    parent: &MonteCarloTreeNode<G>,
+    child: &MonteCarloTreeNode<G>,
+) -> f64 {
+    if parent.is_root() {
+        parent.games
+    } else {
+        *parent
+            .choice_availability_count
+            .get(child.choice.as_ref().unwrap())
+            .unwrap() as f64
+    }
+}
+
+// The choice `monte_carlo_tree_search`/`Game::run_with_reuse` commit to: a
+// proven win always beats a merely well-visited child, since no amount of
+// extra visits elsewhere changes that it's already decided.
+pub(crate) fn most_promising_choice<G: Game>(tree: &MonteCarloTreeNode<G>) -> G::Choice {
+    tree.children
+        .values()
+        .find(|child| child.proven == Some(Ordering::Greater))
+        .unwrap_or_else(|| {
+            tree.children
+                .values()
+                .max_by_key(|child| FloatOrd(child.games))
+                .unwrap()
+        })
+        .choice
+        .clone()
+        .unwrap()
+}
+
+// A terminal node is proven for `player_id` when its reward is an
+// unambiguous win or loss; anything else (e.g. a draw) is left unproven.
+//
+// Games that aren't `is_deterministic` never produce a proof at all: under
+// determinization a terminal's reward reflects that one iteration's sampled
+// world rather than a fact about the real game, so caching it as "proven"
+// would lock in a single coin flip and starve the node's other children of
+// the rest of the search budget.
+pub(crate) fn proven_outcome_for<G: Game>(game: &G, player_id: G::PlayerId) -> Option<Ordering> {
+    if !game.is_deterministic() {
+        return None;
+    }
+    let reward = game.reward_for(player_id);
+    if reward >= 1.0 {
+        Some(Ordering::Greater)
+    } else if reward <= 0.0 {
+        Some(Ordering::Less)
+    } else {
+        None
+    }
+}
+
 pub fn upper_confidence_bound(
     cumulative_reward: f64,
     games: f64,
@@ -287,21 +482,22 @@ pub fn upper_confidence_bound(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{BinaryTreeDepthThreeZeroWins, CustomGameTree};
 
     #[derive(Clone)]
-    pub struct ThreeBranchThreeDepthAlwaysWin {
+    pub struct ThreeBranchThreeDepthUniformReward {
         turn_number: usize,
     }
 
-    impl ThreeBranchThreeDepthAlwaysWin {
+    impl ThreeBranchThreeDepthUniformReward {
         pub fn new() -> Self {
-            ThreeBranchThreeDepthAlwaysWin {
+            ThreeBranchThreeDepthUniformReward {
                 turn_number: 0,
             }
         }
     }
 
-    impl Game for ThreeBranchThreeDepthAlwaysWin {
+    impl Game for ThreeBranchThreeDepthUniformReward {
         type Choice = usize;
 
         type PlayerId = usize;
@@ -323,203 +519,46 @@ mod tests {
         }
 
         fn reward_for(&self, _player_id: Self::PlayerId) -> f64 {
-            1.0
+            // Deliberately not 0.0 or 1.0: these tests exercise plain UCB1
+            // exploration, so the reward must stay unprovable or the
+            // MCTS-Solver shortcut in `select` would stop exploration early.
+            0.5
         }
     }
 
     #[test]
     fn test_explores_each_option_once() {
-        let game = ThreeBranchThreeDepthAlwaysWin::new();
-        let mut mcts: VanillaMcts<ThreeBranchThreeDepthAlwaysWin> = VanillaMcts::new();
-        let tree = mcts.build_tree(game.clone(), 3);
+        let game = ThreeBranchThreeDepthUniformReward::new();
+        let mut mcts: VanillaMcts<ThreeBranchThreeDepthUniformReward> = VanillaMcts::new();
+        let tree = mcts.build_tree(game.clone(), Budget::Iterations(3));
         assert_eq!(tree.children.len(), 3);
         assert!(tree.children.iter().all(|(_, child)| child.games == 1.0));
-        assert!(tree.children.iter().all(|(_, child)| child.cumulative_reward == 1.0));
+        assert!(tree.children.iter().all(|(_, child)| child.cumulative_reward == 0.5));
     }
 
     #[test]
     fn test_even_exploration() {
-        let game = ThreeBranchThreeDepthAlwaysWin::new();
-        let mut mcts: VanillaMcts<ThreeBranchThreeDepthAlwaysWin> = VanillaMcts::new();
-        let tree = mcts.build_tree(game.clone(), 3 * 3 * 3 * 3);
+        let game = ThreeBranchThreeDepthUniformReward::new();
+        let mut mcts: VanillaMcts<ThreeBranchThreeDepthUniformReward> = VanillaMcts::new();
+        let tree = mcts.build_tree(game.clone(), Budget::Iterations(3 * 3 * 3 * 3));
         assert_eq!(tree.children.len(), 3);
         assert!(tree.children.iter().all(|(_, child)| child.games == 3.0 * 3.0 * 3.0));
-        assert!(tree.children.iter().all(|(_, child)| child.cumulative_reward == 3.0 * 3.0 * 3.0));
-    }
-
-    #[derive(Clone)]
-    pub struct BinaryTreeDepthThreeZeroWins {
-        turn_number: usize,
-        points: usize,
-    }
-
-    impl BinaryTreeDepthThreeZeroWins {
-        pub fn new() -> Self {
-            BinaryTreeDepthThreeZeroWins {
-                turn_number: 0,
-                points: 0,
-            }
-        }
-    }
-
-    impl Game for BinaryTreeDepthThreeZeroWins {
-        type Choice = usize;
-
-        type PlayerId = usize;
-
-        fn get_all_choices(&self) -> Vec<Self::Choice> {
-            return vec![0, 1];
-        }
-
-        fn apply_choice(&mut self, choice: &Self::Choice) {
-            println!("{}", choice);
-            self.turn_number += 1;
-            if *choice == 1 {
-                self.points += 1;
-            }
-        }
-
-        fn get_active_player_id(&self) -> Self::PlayerId {
-            return 1;
-        }
-
-        fn is_terminal(&self) -> bool {
-            self.turn_number >= 3
-        }
-
-        fn reward_for(&self, _player_id: Self::PlayerId) -> f64 {
-            if self.points == 3 { 1.0 } else { 0.0 }
-        }
-
-        fn shuffle_on_expand(&self) -> bool {
-            false
-        }
+        assert!(tree.children.iter().all(|(_, child)| child.cumulative_reward == 3.0 * 3.0 * 3.0 * 0.5));
     }
 
     #[test]
     fn finds_best() {
         let game = BinaryTreeDepthThreeZeroWins::new();
         let mut mcts: VanillaMcts<BinaryTreeDepthThreeZeroWins> = VanillaMcts::new();
-        let (choice, _) = mcts.monte_carlo_tree_search(game.clone(), 8);
+        let (choice, _) = mcts.monte_carlo_tree_search(game.clone(), Budget::Iterations(8));
         assert_eq!(choice, 1);
     }
 
-    #[derive(Clone)]
-    pub struct GameNode {
-        children: Vec<GameNode>,
-        winner: Option<usize>,
-    }
-
-    impl GameNode {
-        pub fn new(children: Vec<GameNode>, winner: Option<usize>) -> Self {
-            GameNode {
-                children,
-                winner,
-            }
-        }
-
-        pub fn you_choose(children: Vec<GameNode>) -> Self {
-            GameNode {
-                children,
-                winner: None,
-            }
-        }
-
-        pub fn they_choose(children: Vec<GameNode>) -> Self {
-            GameNode {
-                children,
-                winner: None,
-            }
-        }
-
-        pub fn winner(player_id: usize) -> Self {
-            GameNode {
-                children: vec![],
-                winner: Some(player_id),
-            }
-        }
-    }
-
-    #[derive(Clone)]
-    pub struct CustomGameTree {
-        state: GameNode,
-        active_player: usize,
-        player_count: usize,
-    }
-
-    impl CustomGameTree {
-        pub fn minimal_trap() -> Self {
-            let you = 0;
-            let them = 1;
-            CustomGameTree {
-                state: GameNode::you_choose(vec![
-                    // If you chose this node it seems like you have 2/3 win chance, but you always lose
-                    GameNode::they_choose(vec![
-                        GameNode::winner(you),
-                        GameNode::winner(you),
-                        GameNode::winner(them),
-                    ]),
-                    // If you chose this node it seems like you have 1/3 win chance, but you always win
-                    GameNode::they_choose(vec![
-                        GameNode::you_choose(vec![
-                            GameNode::winner(you),
-                            GameNode::winner(them),
-                            GameNode::winner(them),
-                        ]),
-                        GameNode::you_choose(vec![
-                            GameNode::winner(you),
-                            GameNode::winner(them),
-                            GameNode::winner(them),
-                        ]),
-                        GameNode::you_choose(vec![
-                            GameNode::winner(you),
-                            GameNode::winner(them),
-                            GameNode::winner(them),
-                        ]),
-                    ]),
-                ]),
-                active_player: you,
-                player_count: 2,
-            }
-        }
-    }
-
-    impl Game for CustomGameTree {
-        type Choice = usize;
-
-        type PlayerId = usize;
-
-        fn get_all_choices(&self) -> Vec<Self::Choice> {
-            (0..self.state.children.len()).collect()
-        }
-
-        fn apply_choice(&mut self, choice: &Self::Choice) {
-            self.state = self.state.children.remove(*choice);
-            self.active_player = (self.active_player + 1) % self.player_count;
-        }
-
-        fn get_active_player_id(&self) -> Self::PlayerId {
-            return self.active_player;
-        }
-
-        fn is_terminal(&self) -> bool {
-            self.state.winner.is_some()
-        }
-
-        fn reward_for(&self, player_id: Self::PlayerId) -> f64 {
-            if self.state.winner.unwrap() == player_id { 1.0 } else { 0.0 }
-        }
-
-        fn shuffle_on_expand(&self) -> bool {
-            false
-        }
-    }
-
     #[test]
     fn defeats_trap() {
         let game = CustomGameTree::minimal_trap();
         let mut mcts: VanillaMcts<CustomGameTree> = VanillaMcts::new();
-        let (choice, _) = mcts.monte_carlo_tree_search(game.clone(), 40);
+        let (choice, _) = mcts.monte_carlo_tree_search(game.clone(), Budget::Iterations(40));
         assert_eq!(choice, 1);
     }
 }
@@ -0,0 +1,371 @@
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::thread;
+
+use float_ord::FloatOrd;
+
+use crate::monte_carlo::{most_promising_choice, Budget, MonteCarloTreeNode, MonteCarloTreeSearch, VanillaMcts};
+use crate::stats::MctsStats;
+use crate::Game;
+
+/// How work should be split across threads in [`ParallelMcts`].
+pub enum ParallelStrategy {
+    /// Build `threads` independent trees in parallel, each with its own share
+    /// of the iteration budget, then sum their root-level statistics before
+    /// picking a proven-win child if any tree found one, else the
+    /// most-visited child. Simple and embarrassingly parallel,
+    /// but wastes some exploration because the trees never share information.
+    Root { threads: usize },
+    /// Build a single tree shared by `threads` worker threads, guarded by a
+    /// lock. While a thread descends through the tree it books a temporary
+    /// `virtual_loss` against every node on its path so that other threads
+    /// are steered toward less-contested children instead of repeating the
+    /// same descent.
+    Tree { threads: usize, virtual_loss: f64 },
+}
+
+/// Multithreaded Monte Carlo tree search.
+///
+/// Both strategies rely on `game.get_rollout_choice()`/`thread_rng()` already
+/// handing out a thread-local RNG (the `rand` crate caches one per OS
+/// thread), so no extra plumbing is needed to keep workers from contending
+/// over a shared generator.
+pub struct ParallelMcts<G: Game> {
+    phantom: PhantomData<G>,
+}
+
+impl<G: Game> ParallelMcts<G> {
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<G: Game> Default for ParallelMcts<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> ParallelMcts<G>
+where
+    G: Game + Send + Sync,
+    G::Choice: Send + Sync,
+    G::PlayerId: Send + Sync,
+{
+    pub fn monte_carlo_tree_search_parallel(
+        &mut self,
+        game: G,
+        iterations: usize,
+        strategy: ParallelStrategy,
+    ) -> (G::Choice, MctsStats) {
+        let tree = self.build_tree_parallel(game, iterations, strategy);
+
+        let selected_choice = most_promising_choice(&tree);
+        let selected_child = &tree.children[&selected_choice];
+
+        (
+            selected_child.choice.clone().unwrap(),
+            MctsStats {
+                tree_cumulative_reward: tree.cumulative_reward,
+                tree_games: tree.games,
+            },
+        )
+    }
+
+    pub fn build_tree_parallel(
+        &mut self,
+        game: G,
+        iterations: usize,
+        strategy: ParallelStrategy,
+    ) -> MonteCarloTreeNode<G> {
+        match strategy {
+            ParallelStrategy::Root { threads } => {
+                self.build_tree_root_parallel(game, iterations, threads)
+            }
+            ParallelStrategy::Tree { threads, virtual_loss } => {
+                build_tree_tree_parallel(game, iterations, threads, virtual_loss)
+            }
+        }
+    }
+
+    fn build_tree_root_parallel(
+        &mut self,
+        game: G,
+        iterations: usize,
+        threads: usize,
+    ) -> MonteCarloTreeNode<G> {
+        let threads = threads.max(1);
+
+        let trees: Vec<MonteCarloTreeNode<G>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|thread_index| {
+                    let game = game.clone();
+                    let iterations_for_thread = share_of_iterations(iterations, threads, thread_index);
+                    scope.spawn(move || {
+                        let mut mcts: VanillaMcts<G> = VanillaMcts::new();
+                        mcts.build_tree(game, Budget::Iterations(iterations_for_thread))
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        merge_trees(trees)
+    }
+}
+
+// Splits `iterations` across `threads` as evenly as possible: integer
+// division alone would silently drop up to `threads - 1` iterations, so the
+// remainder is handed out one each to the first `iterations % threads`
+// threads instead.
+fn share_of_iterations(iterations: usize, threads: usize, thread_index: usize) -> usize {
+    let base = iterations / threads;
+    let remainder = iterations % threads;
+    base + if thread_index < remainder { 1 } else { 0 }
+}
+
+fn merge_trees<G: Game>(mut trees: Vec<MonteCarloTreeNode<G>>) -> MonteCarloTreeNode<G> {
+    let mut merged = trees.pop().expect("at least one tree to merge");
+    for tree in trees {
+        merged.games += tree.games;
+        merged.cumulative_reward += tree.cumulative_reward;
+        merged.sum_squared_reward += tree.sum_squared_reward;
+        for (choice, child) in tree.children {
+            merged
+                .children
+                .entry(choice)
+                .and_modify(|existing| {
+                    existing.games += child.games;
+                    existing.cumulative_reward += child.cumulative_reward;
+                    existing.sum_squared_reward += child.sum_squared_reward;
+                    // A proof found by any one thread is real regardless of
+                    // which tree it came from: treat `Some` as sticky and
+                    // `None` as the identity, instead of letting whichever
+                    // tree happens to merge last silently overwrite it.
+                    existing.proven = existing.proven.or(child.proven);
+                })
+                .or_insert(child);
+        }
+    }
+    merged
+}
+
+fn build_tree_tree_parallel<G>(
+    game: G,
+    iterations: usize,
+    threads: usize,
+    virtual_loss: f64,
+) -> MonteCarloTreeNode<G>
+where
+    G: Game + Send + Sync,
+    G::Choice: Send + Sync,
+    G::PlayerId: Send + Sync,
+{
+    let threads = threads.max(1);
+    let player_id = game.get_active_player_id();
+    let tree = Mutex::new(MonteCarloTreeNode::new(player_id, None));
+
+    thread::scope(|scope| {
+        for thread_index in 0..threads {
+            let tree = &tree;
+            let game = game.clone();
+            let iterations_for_thread = share_of_iterations(iterations, threads, thread_index);
+            scope.spawn(move || {
+                for _ in 0..iterations_for_thread {
+                    let determinization = game.get_determinization(game.get_active_player_id());
+                    iterate_shared_tree(tree, determinization, virtual_loss);
+                }
+            });
+        }
+    });
+
+    tree.into_inner().unwrap()
+}
+
+// One iteration against a tree shared by every worker thread: select a path
+// one node at a time, each under its own brief lock (booking a virtual loss
+// along the way so other threads diverge), run the rollout unlocked, then
+// lock once more to undo the virtual loss and record the real outcome.
+//
+// The lock is reacquired at every level of the descent rather than held for
+// the whole path, so a thread only ever blocks others for the duration of a
+// single node's selection instead of serializing the entire compute-heavy
+// select phase behind one thread's full walk to the frontier.
+fn iterate_shared_tree<G: Game>(tree_lock: &Mutex<MonteCarloTreeNode<G>>, game: G, virtual_loss: f64) {
+    let policy: VanillaMcts<G> = VanillaMcts::new();
+
+    let mut path: Vec<G::Choice> = Vec::new();
+    let mut game = game;
+    let mut reached_new_child = false;
+    loop {
+        if game.is_terminal() {
+            break;
+        }
+        let was_unvisited = {
+            let mut tree = tree_lock.lock().unwrap();
+            let node = walk_to_mut(&mut *tree, &path);
+            node.expand(&game, game.shuffle_on_expand());
+            let (choice, was_unvisited) = select_with_virtual_loss(&policy, node, &game, virtual_loss);
+            game.apply_choice(&choice);
+            path.push(choice);
+            was_unvisited
+        };
+        if was_unvisited {
+            reached_new_child = true;
+            break;
+        }
+    }
+
+    let game = if reached_new_child {
+        rollout_without_tree(game)
+    } else {
+        game
+    };
+
+    let mut tree = tree_lock.lock().unwrap();
+    let mut node = &mut *tree;
+    record_outcome(node, &game);
+    for choice in &path {
+        node = node.children.get_mut(choice).unwrap();
+        node.games -= virtual_loss;
+        record_outcome(node, &game);
+    }
+}
+
+// Walks from `tree`'s root down through `path`, one already-expanded choice
+// at a time, to the node the next selection step should act on.
+fn walk_to_mut<'a, G: Game>(
+    tree: &'a mut MonteCarloTreeNode<G>,
+    path: &[G::Choice],
+) -> &'a mut MonteCarloTreeNode<G> {
+    let mut node = tree;
+    for choice in path {
+        node = node.children.get_mut(choice).unwrap();
+    }
+    node
+}
+
+fn record_outcome<G: Game>(node: &mut MonteCarloTreeNode<G>, game: &G) {
+    let reward = game.reward_for(node.player_id);
+    node.cumulative_reward += reward;
+    node.sum_squared_reward += reward * reward;
+    node.games += 1.0;
+}
+
+// Picks the same child `MonteCarloTreeSearch::select` would, but also books a
+// virtual loss against it so concurrently descending threads prefer other
+// children. Returns whether the child had not yet been visited.
+fn select_with_virtual_loss<G: Game>(
+    policy: &VanillaMcts<G>,
+    node: &mut MonteCarloTreeNode<G>,
+    game: &G,
+    virtual_loss: f64,
+) -> (G::Choice, bool) {
+    let chosen = node
+        .children
+        .iter()
+        .filter(|(choice, _)| game.choice_is_available(choice))
+        .max_by_key(|(_, child)| {
+            FloatOrd(if child.games == 0.0 {
+                policy.get_first_play_value(game, node, child, &None)
+            } else {
+                policy.get_selection_value(game, node, child)
+            })
+        })
+        .map(|(choice, _)| choice.clone())
+        .unwrap();
+
+    let child = node.children.get_mut(&chosen).unwrap();
+    let was_unvisited = child.games == 0.0;
+    child.games += virtual_loss;
+    (chosen, was_unvisited)
+}
+
+fn rollout_without_tree<G: Game>(mut game: G) -> G {
+    while !game.is_terminal() && !game.heuristic_early_terminate() {
+        let choice = game.get_rollout_choice();
+        game.apply_choice(&choice);
+    }
+    game
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{BinaryTreeDepthThreeZeroWins, CustomGameTree};
+    use std::cmp::Ordering;
+
+    #[test]
+    fn merge_trees_sums_stats_and_keeps_proven_status_regardless_of_order() {
+        let mut proven_child: MonteCarloTreeNode<BinaryTreeDepthThreeZeroWins> =
+            MonteCarloTreeNode::new(1, Some(1));
+        proven_child.games = 3.0;
+        proven_child.cumulative_reward = 3.0;
+        proven_child.proven = Some(Ordering::Greater);
+
+        let mut unproven_child: MonteCarloTreeNode<BinaryTreeDepthThreeZeroWins> =
+            MonteCarloTreeNode::new(1, Some(1));
+        unproven_child.games = 5.0;
+        unproven_child.cumulative_reward = 2.0;
+
+        let mut tree_with_proof: MonteCarloTreeNode<BinaryTreeDepthThreeZeroWins> =
+            MonteCarloTreeNode::new(1, None);
+        tree_with_proof.children.insert(1, proven_child);
+
+        let mut tree_without_proof: MonteCarloTreeNode<BinaryTreeDepthThreeZeroWins> =
+            MonteCarloTreeNode::new(1, None);
+        tree_without_proof.children.insert(1, unproven_child);
+
+        // `merge_trees` keeps the last tree as the base, so the unproven tree
+        // is merged into the proven one; a merge that let the merged-in
+        // tree's proof overwrite the base's would lose it here.
+        let merged = merge_trees(vec![tree_without_proof, tree_with_proof]);
+        let merged_child = &merged.children[&1];
+        assert_eq!(merged_child.games, 8.0);
+        assert_eq!(merged_child.cumulative_reward, 5.0);
+        assert_eq!(merged_child.proven, Some(Ordering::Greater));
+    }
+
+    #[test]
+    fn build_tree_root_parallel_merges_every_threads_iterations() {
+        let game = BinaryTreeDepthThreeZeroWins::new();
+        let mut mcts: ParallelMcts<BinaryTreeDepthThreeZeroWins> = ParallelMcts::new();
+        let tree = mcts.build_tree_parallel(game, 40, ParallelStrategy::Root { threads: 4 });
+
+        assert_eq!(tree.games, 40.0);
+        let total_child_games: f64 = tree.children.values().map(|child| child.games).sum();
+        assert_eq!(total_child_games, 40.0);
+    }
+
+    #[test]
+    fn root_parallel_finds_best() {
+        let game = BinaryTreeDepthThreeZeroWins::new();
+        let mut mcts: ParallelMcts<BinaryTreeDepthThreeZeroWins> = ParallelMcts::new();
+        let (choice, _) = mcts.monte_carlo_tree_search_parallel(
+            game,
+            80,
+            ParallelStrategy::Root { threads: 4 },
+        );
+        assert_eq!(choice, 1);
+    }
+
+    #[test]
+    fn tree_parallel_defeats_trap() {
+        let game = CustomGameTree::minimal_trap();
+        let mut mcts: ParallelMcts<CustomGameTree> = ParallelMcts::new();
+        let (choice, _) = mcts.monte_carlo_tree_search_parallel(
+            game,
+            200,
+            ParallelStrategy::Tree {
+                threads: 4,
+                virtual_loss: 1.0,
+            },
+        );
+        assert_eq!(choice, 1);
+    }
+}
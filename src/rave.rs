@@ -0,0 +1,200 @@
+use std::marker::PhantomData;
+
+use crate::monte_carlo::{
+    proven_outcome_for, total_available_games, upper_confidence_bound, MonteCarloTreeNode,
+    MonteCarloTreeSearch,
+};
+use crate::Game;
+
+/// MCTS augmented with Rapid Action Value Estimation (RAVE/AMAF).
+///
+/// Besides a child's own UCB1 statistics, its selection value is blended
+/// with an "all moves as first" estimate built from every occurrence of
+/// that same choice anywhere later in the playout, by the same player, even
+/// if it wasn't played directly from this node. Games with transposable
+/// moves (the same `Choice` is meaningful across plies) converge faster
+/// this way, at the cost of some bias while few real visits have
+/// accumulated.
+pub struct RaveMcts<G: Game>
+where
+    G::PlayerId: PartialEq,
+{
+    phantom: PhantomData<G>,
+    // Equivalence parameter: how many visits the parent needs before a
+    // child's own statistics are trusted as much as its AMAF estimate.
+    k: f64,
+    // The (player, choice) pairs played so far in the current playout, in
+    // order; used to credit AMAF stats for nodes as we unwind back through
+    // them.
+    playout: Vec<(G::PlayerId, G::Choice)>,
+}
+
+impl<G: Game> RaveMcts<G>
+where
+    G::PlayerId: PartialEq,
+{
+    pub fn new(k: f64) -> Self {
+        Self {
+            phantom: PhantomData,
+            k,
+            playout: Vec::new(),
+        }
+    }
+}
+
+impl<G: Game> Default for RaveMcts<G>
+where
+    G::PlayerId: PartialEq,
+{
+    fn default() -> Self {
+        Self::new(1000.0)
+    }
+}
+
+impl<G: Game> MonteCarloTreeSearch for RaveMcts<G>
+where
+    G::PlayerId: PartialEq,
+{
+    type Game = G;
+
+    // `playout` only needs to be empty at the start of each iteration; since
+    // `grow_tree`'s loop calls `after_iteration` right after `iteration`,
+    // clearing it here leaves it ready for the next iteration (and for the
+    // very first, since the constructor already starts it empty). This
+    // keeps RAVE working even if `grow_tree` is ever called directly, e.g.
+    // by `Game::run_with_reuse`.
+    fn after_iteration(&mut self, _game: &Self::Game) {
+        self.playout.clear();
+    }
+
+    fn iteration(
+        &mut self,
+        node: &mut MonteCarloTreeNode<Self::Game>,
+        mut game: Self::Game,
+    ) -> Self::Game {
+        if game.is_terminal() {
+            self.record_outcome(node, &game);
+            node.proven = node.proven.or_else(|| proven_outcome_for(&game, node.player_id));
+            return game;
+        }
+
+        let game_at_node = game.clone();
+        let choices = node.expand(&game, game.shuffle_on_expand());
+        let best_child = self.select(node, &game, choices);
+        self.after_selection(&game, best_child);
+        game.apply_choice(best_child.choice.as_ref().unwrap());
+
+        let mover = best_child.player_id;
+        let choice = best_child.choice.clone().unwrap();
+        let suffix_start = self.playout.len();
+        self.playout.push((mover, choice));
+
+        let game = if best_child.games == 0.0 {
+            let reached_terminal = game.is_terminal();
+            let game = self.rollout(best_child, game);
+            self.record_outcome(best_child, &game);
+            if reached_terminal {
+                best_child.proven = proven_outcome_for(&game, best_child.player_id);
+            }
+            game
+        } else {
+            self.iteration(best_child, game)
+        };
+
+        self.record_outcome(node, &game);
+        node.update_proven_status(&game_at_node);
+        let suffix: Vec<(G::PlayerId, G::Choice)> = self.playout[suffix_start..].to_vec();
+        credit_amaf(node, mover, &game, &suffix);
+        game
+    }
+
+    fn rollout(
+        &mut self,
+        node: &mut MonteCarloTreeNode<Self::Game>,
+        mut game: Self::Game,
+    ) -> Self::Game {
+        while !game.is_terminal() && !game.heuristic_early_terminate() {
+            let choice = game.get_rollout_choice();
+            let choice = self.intercept_rollout_choice(node, &mut game, choice);
+            self.playout.push((game.get_active_player_id(), choice.clone()));
+            game.apply_choice(&choice);
+        }
+        game
+    }
+
+    fn get_selection_value(
+        &self,
+        _game: &Self::Game,
+        parent: &MonteCarloTreeNode<Self::Game>,
+        child: &MonteCarloTreeNode<Self::Game>,
+    ) -> f64 {
+        let c = 0.4;
+        let ucb = upper_confidence_bound(
+            child.cumulative_reward,
+            child.games,
+            total_available_games(parent, child),
+            c,
+        );
+
+        match parent.amaf.get(child.choice.as_ref().unwrap()) {
+            Some((amaf_games, amaf_reward)) if *amaf_games > 0.0 => {
+                let beta = f64::sqrt(self.k / (3.0 * parent.games + self.k));
+                (1.0 - beta) * ucb + beta * (amaf_reward / amaf_games)
+            }
+            _ => ucb,
+        }
+    }
+}
+
+// Credits every choice in `suffix` that was made by `mover` with the final
+// playout outcome, regardless of which of `node`'s children actually led
+// there.
+fn credit_amaf<G: Game>(
+    node: &mut MonteCarloTreeNode<G>,
+    mover: G::PlayerId,
+    game: &G,
+    suffix: &[(G::PlayerId, G::Choice)],
+) where
+    G::PlayerId: PartialEq,
+{
+    let reward = game.reward_for(mover);
+    for (player_id, choice) in suffix {
+        if *player_id == mover {
+            let entry = node.amaf.entry(choice.clone()).or_insert((0.0, 0.0));
+            entry.0 += 1.0;
+            entry.1 += reward;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monte_carlo::Budget;
+    use crate::test_support::{BinaryTreeDepthThreeZeroWins, CustomGameTree};
+
+    #[test]
+    fn finds_best() {
+        let game = BinaryTreeDepthThreeZeroWins::new();
+        let mut mcts: RaveMcts<BinaryTreeDepthThreeZeroWins> = RaveMcts::new(50.0);
+        let (choice, _) = mcts.monte_carlo_tree_search(game.clone(), Budget::Iterations(16));
+        assert_eq!(choice, 1);
+    }
+
+    #[test]
+    fn defeats_trap() {
+        let game = CustomGameTree::minimal_trap();
+        let mut mcts: RaveMcts<CustomGameTree> = RaveMcts::new(50.0);
+        let (choice, _) = mcts.monte_carlo_tree_search(game.clone(), Budget::Iterations(40));
+        assert_eq!(choice, 1);
+    }
+
+    #[test]
+    fn credits_amaf_for_repeated_choices() {
+        let game = BinaryTreeDepthThreeZeroWins::new();
+        let mut mcts: RaveMcts<BinaryTreeDepthThreeZeroWins> = RaveMcts::new(50.0);
+        let tree = mcts.build_tree(game.clone(), Budget::Iterations(16));
+        let child = &tree.children[&1];
+        assert!(child.amaf.get(&1).map_or(false, |(games, _)| *games > 0.0));
+    }
+}
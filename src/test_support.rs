@@ -0,0 +1,167 @@
+// Fixtures shared by unit tests across the crate, so each module's tests
+// don't paste their own copy of the same `Game` impl.
+use crate::Game;
+
+#[derive(Clone)]
+pub(crate) struct BinaryTreeDepthThreeZeroWins {
+    turn_number: usize,
+    points: usize,
+}
+
+impl BinaryTreeDepthThreeZeroWins {
+    pub(crate) fn new() -> Self {
+        BinaryTreeDepthThreeZeroWins {
+            turn_number: 0,
+            points: 0,
+        }
+    }
+}
+
+impl Game for BinaryTreeDepthThreeZeroWins {
+    type Choice = usize;
+
+    type PlayerId = usize;
+
+    fn get_all_choices(&self) -> Vec<Self::Choice> {
+        vec![0, 1]
+    }
+
+    fn apply_choice(&mut self, choice: &Self::Choice) {
+        self.turn_number += 1;
+        if *choice == 1 {
+            self.points += 1;
+        }
+    }
+
+    fn get_active_player_id(&self) -> Self::PlayerId {
+        1
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.turn_number >= 3
+    }
+
+    fn reward_for(&self, _player_id: Self::PlayerId) -> f64 {
+        if self.points == 3 {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn shuffle_on_expand(&self) -> bool {
+        false
+    }
+}
+
+// A two-player game tree laid out explicitly, used to exercise the
+// MCTS-Solver trap where a naive win-rate estimate picks the wrong move:
+// one branch looks like a 2/3 win chance but always loses, while the other
+// looks like 1/3 but always wins once `you` plays correctly.
+#[derive(Clone)]
+pub(crate) struct GameNode {
+    children: Vec<GameNode>,
+    winner: Option<usize>,
+}
+
+impl GameNode {
+    pub(crate) fn you_choose(children: Vec<GameNode>) -> Self {
+        GameNode {
+            children,
+            winner: None,
+        }
+    }
+
+    pub(crate) fn they_choose(children: Vec<GameNode>) -> Self {
+        GameNode {
+            children,
+            winner: None,
+        }
+    }
+
+    pub(crate) fn winner(player_id: usize) -> Self {
+        GameNode {
+            children: vec![],
+            winner: Some(player_id),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct CustomGameTree {
+    state: GameNode,
+    active_player: usize,
+    player_count: usize,
+}
+
+impl CustomGameTree {
+    pub(crate) fn minimal_trap() -> Self {
+        let you = 0;
+        let them = 1;
+        CustomGameTree {
+            state: GameNode::you_choose(vec![
+                // If you chose this node it seems like you have 2/3 win chance, but you always lose
+                GameNode::they_choose(vec![
+                    GameNode::winner(you),
+                    GameNode::winner(you),
+                    GameNode::winner(them),
+                ]),
+                // If you chose this node it seems like you have 1/3 win chance, but you always win
+                GameNode::they_choose(vec![
+                    GameNode::you_choose(vec![
+                        GameNode::winner(you),
+                        GameNode::winner(them),
+                        GameNode::winner(them),
+                    ]),
+                    GameNode::you_choose(vec![
+                        GameNode::winner(you),
+                        GameNode::winner(them),
+                        GameNode::winner(them),
+                    ]),
+                    GameNode::you_choose(vec![
+                        GameNode::winner(you),
+                        GameNode::winner(them),
+                        GameNode::winner(them),
+                    ]),
+                ]),
+            ]),
+            active_player: you,
+            player_count: 2,
+        }
+    }
+}
+
+impl Game for CustomGameTree {
+    type Choice = usize;
+
+    type PlayerId = usize;
+
+    fn get_all_choices(&self) -> Vec<Self::Choice> {
+        (0..self.state.children.len()).collect()
+    }
+
+    fn apply_choice(&mut self, choice: &Self::Choice) {
+        self.state = self.state.children.remove(*choice);
+        self.active_player = (self.active_player + 1) % self.player_count;
+    }
+
+    fn get_active_player_id(&self) -> Self::PlayerId {
+        self.active_player
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.state.winner.is_some()
+    }
+
+    fn reward_for(&self, player_id: Self::PlayerId) -> f64 {
+        if self.state.winner.unwrap() == player_id {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn shuffle_on_expand(&self) -> bool {
+        false
+    }
+}